@@ -0,0 +1,473 @@
+use crate::webauthn::error::Error;
+use crate::webauthn::proto::constants::{
+    WEBAUTHN_COSE_ALGORITHM_IDENTIFIER_EDDSA, WEBAUTHN_COSE_ALGORITHM_IDENTIFIER_ES256,
+    WEBAUTHN_COSE_ALGORITHM_IDENTIFIER_ES384, WEBAUTHN_COSE_ALGORITHM_IDENTIFIER_ES512,
+    WEBAUTHN_COSE_ALGORITHM_IDENTIFIER_RS256, WEBAUTHN_COSE_CURVE_P256, WEBAUTHN_COSE_CURVE_P384,
+    WEBAUTHN_COSE_CURVE_P521,
+};
+use crate::webauthn::metadata::MetadataService;
+use crate::webauthn::proto::raw_message::{
+    AttestationObject, AttestedCredentialData, Coordinates, CredentialPublicKey, KeyMaterial,
+};
+use openssl::bn::BigNum;
+use openssl::ec::{EcGroup, EcKey};
+use openssl::hash::MessageDigest;
+use openssl::nid::Nid;
+use openssl::pkey::{Id, PKey, Public};
+use openssl::rsa::Rsa;
+use openssl::sign::Verifier;
+use openssl::stack::Stack;
+use openssl::x509::store::X509StoreBuilder;
+use openssl::x509::{X509StoreContext, X509};
+use asn1_rs::oid;
+use serde_cbor::Value;
+
+/// The certificate chain (if any) presented by an authenticator during attestation,
+/// in leaf-first order, so callers can run their own trust-anchor checks.
+#[derive(Debug, Clone)]
+pub struct AttestationTrustPath {
+    pub certificates: Vec<X509>,
+}
+
+impl AttestationTrustPath {
+    /// Validates this trust path's leaf certificate against the attestation roots registered
+    /// for `aaguid` in `metadata`, returning the authenticator's model description on success.
+    /// Relying parties use this to allow or deny specific authenticator models by policy.
+    pub fn validate_against_metadata(&self, aaguid: &[u8; 16], metadata: &MetadataService) -> Result<String, Error> {
+        let entry = metadata
+            .metadata_for(aaguid)
+            .ok_or_else(|| Error::Other("no metadata entry registered for this AAGUID".to_string()))?;
+
+        let leaf = self
+            .certificates
+            .first()
+            .ok_or_else(|| Error::CertificateError("no attestation certificate to validate".to_string()))?;
+
+        let mut store_builder = X509StoreBuilder::new().map_err(|e| Error::CertificateError(e.to_string()))?;
+        for root in &entry.attestation_root_certificates {
+            store_builder
+                .add_cert(root.clone())
+                .map_err(|e| Error::CertificateError(e.to_string()))?;
+        }
+        let store = store_builder.build();
+
+        let mut chain = Stack::new().map_err(|e| Error::CertificateError(e.to_string()))?;
+        for intermediate in self.certificates.iter().skip(1) {
+            chain
+                .push(intermediate.clone())
+                .map_err(|e| Error::CertificateError(e.to_string()))?;
+        }
+
+        let mut ctx = X509StoreContext::new().map_err(|e| Error::CertificateError(e.to_string()))?;
+        let trusted = ctx
+            .init(&store, leaf, &chain, |c| c.verify_cert())
+            .map_err(|e| Error::CertificateError(e.to_string()))?;
+
+        if trusted {
+            Ok(entry.description.clone())
+        } else {
+            Err(Error::CertificateError(
+                "attestation certificate does not chain to a registered MDS root".to_string(),
+            ))
+        }
+    }
+}
+
+impl AttestationObject {
+    pub fn verify(&self, client_data_hash: &[u8]) -> Result<AttestationTrustPath, Error> {
+        match self.fmt.as_str() {
+            "packed" => self.verify_packed(client_data_hash),
+            "fido-u2f" => self.verify_fido_u2f(client_data_hash),
+            other => Err(Error::UnsupportedAttestationFormat(other.to_string())),
+        }
+    }
+
+    /// Verifies the attestation statement, then, if the authenticator's AAGUID is registered
+    /// in `metadata`, validates the returned trust path against that entry's roots. Returns the
+    /// trust path alongside the authenticator's model description when metadata was applied, or
+    /// `None` when no policy is registered for this AAGUID or the trust path carries no
+    /// certificates (self attestation), in which case no metadata check is made.
+    pub fn verify_with_metadata(
+        &self,
+        client_data_hash: &[u8],
+        metadata: &MetadataService,
+    ) -> Result<(AttestationTrustPath, Option<String>), Error> {
+        let trust_path = self.verify(client_data_hash)?;
+
+        let has_metadata_entry = |attested: &AttestedCredentialData| {
+            !trust_path.certificates.is_empty() && metadata.metadata_for(&attested.aaguid).is_some()
+        };
+
+        let description = match &self.auth_data.attested_credential_data {
+            Some(attested) if has_metadata_entry(attested) => {
+                Some(trust_path.validate_against_metadata(&attested.aaguid, metadata)?)
+            }
+            _ => None,
+        };
+
+        Ok((trust_path, description))
+    }
+
+    fn verify_packed(&self, client_data_hash: &[u8]) -> Result<AttestationTrustPath, Error> {
+        let mut verification_data = self.raw_auth_data.clone();
+        verification_data.extend_from_slice(client_data_hash);
+
+        match &self.att_stmt.x5c {
+            Some(x5c) => {
+                let certificates = decode_x5c(x5c)?;
+                let leaf = certificates
+                    .first()
+                    .ok_or_else(|| Error::CertificateError("x5c chain is empty".to_string()))?;
+
+                let leaf_key = leaf
+                    .public_key()
+                    .map_err(|e| Error::CertificateError(e.to_string()))?;
+                verify_signature(&leaf_key, self.att_stmt.alg, &verification_data, &self.att_stmt.sig)?;
+
+                if leaf.version() != 2 {
+                    return Err(Error::CertificateError(
+                        "attestation certificate must be X.509v3".to_string(),
+                    ));
+                }
+                if !basic_constraints_ca_false(leaf)? {
+                    return Err(Error::CertificateError(
+                        "attestation certificate must not be a CA".to_string(),
+                    ));
+                }
+
+                if let Some(aaguid) = fido_aaguid_extension(leaf)? {
+                    let attested = self
+                        .auth_data
+                        .attested_credential_data
+                        .as_ref()
+                        .ok_or_else(|| Error::Other("no attested credential data present".to_string()))?;
+                    if aaguid != attested.aaguid {
+                        return Err(Error::CertificateError(
+                            "certificate AAGUID does not match authenticator data".to_string(),
+                        ));
+                    }
+                }
+
+                Ok(AttestationTrustPath { certificates })
+            }
+            None => {
+                let attested = self
+                    .auth_data
+                    .attested_credential_data
+                    .as_ref()
+                    .ok_or_else(|| Error::Other("no attested credential data present".to_string()))?;
+
+                if self.att_stmt.alg != attested.credential_public_key.alg {
+                    return Err(Error::Other(
+                        "self attestation algorithm does not match credential public key".to_string(),
+                    ));
+                }
+
+                let credential_key = credential_public_key_to_pkey(&attested.credential_public_key)?;
+                verify_signature(&credential_key, self.att_stmt.alg, &verification_data, &self.att_stmt.sig)?;
+
+                Ok(AttestationTrustPath { certificates: Vec::new() })
+            }
+        }
+    }
+
+    fn verify_fido_u2f(&self, client_data_hash: &[u8]) -> Result<AttestationTrustPath, Error> {
+        let x5c = self
+            .att_stmt
+            .x5c
+            .as_ref()
+            .ok_or_else(|| Error::CertificateError("fido-u2f attestation requires x5c".to_string()))?;
+        let mut certificates = decode_x5c(x5c)?;
+        if certificates.len() != 1 {
+            return Err(Error::CertificateError(
+                "fido-u2f attestation requires exactly one certificate".to_string(),
+            ));
+        }
+        let leaf = certificates.remove(0);
+
+        let attested = self
+            .auth_data
+            .attested_credential_data
+            .as_ref()
+            .ok_or_else(|| Error::Other("no attested credential data present".to_string()))?;
+
+        let coords = match &attested.credential_public_key.key_material {
+            KeyMaterial::Ec2 { coords, .. } => coords,
+            _ => {
+                return Err(Error::Other(
+                    "fido-u2f attestation requires an EC2 public key".to_string(),
+                ))
+            }
+        };
+        let (x, y) = match coords {
+            Coordinates::Uncompressed { x, y } => (x.clone(), y.clone()),
+            Coordinates::Compressed { .. } => {
+                return Err(Error::Other(
+                    "fido-u2f attestation requires an uncompressed EC2 public key".to_string(),
+                ))
+            }
+        };
+
+        let mut public_key_u2f = Vec::with_capacity(65);
+        public_key_u2f.push(0x04);
+        public_key_u2f.extend_from_slice(&x);
+        public_key_u2f.extend_from_slice(&y);
+
+        let mut verification_data = Vec::new();
+        verification_data.push(0x00);
+        verification_data.extend_from_slice(&self.auth_data.rp_id_hash);
+        verification_data.extend_from_slice(client_data_hash);
+        verification_data.extend_from_slice(&attested.credential_id);
+        verification_data.extend_from_slice(&public_key_u2f);
+
+        let leaf_key = leaf
+            .public_key()
+            .map_err(|e| Error::CertificateError(e.to_string()))?;
+        verify_signature(&leaf_key, self.att_stmt.alg, &verification_data, &self.att_stmt.sig)?;
+
+        Ok(AttestationTrustPath { certificates: vec![leaf] })
+    }
+}
+
+fn decode_x5c(x5c: &Value) -> Result<Vec<X509>, Error> {
+    let entries = match x5c {
+        Value::Array(entries) => entries,
+        _ => return Err(Error::CertificateError("x5c is not a CBOR array".to_string())),
+    };
+
+    entries
+        .iter()
+        .map(|entry| match entry {
+            Value::Bytes(der) => X509::from_der(der).map_err(|e| Error::CertificateError(e.to_string())),
+            _ => Err(Error::CertificateError("x5c entry is not a byte string".to_string())),
+        })
+        .collect()
+}
+
+fn verify_signature(key: &PKey<Public>, alg: i64, data: &[u8], signature: &[u8]) -> Result<(), Error> {
+    let valid = if alg == WEBAUTHN_COSE_ALGORITHM_IDENTIFIER_EDDSA {
+        // Ed25519 is a one-shot scheme in openssl; it has no streaming update/verify.
+        let mut verifier = Verifier::new_without_digest(key).map_err(|e| Error::CertificateError(e.to_string()))?;
+        verifier
+            .verify_oneshot(signature, data)
+            .map_err(|e| Error::CertificateError(e.to_string()))?
+    } else {
+        let digest = digest_for_alg(alg)?;
+        let mut verifier = Verifier::new(digest, key).map_err(|e| Error::CertificateError(e.to_string()))?;
+        verifier.update(data).map_err(|e| Error::CertificateError(e.to_string()))?;
+        verifier.verify(signature).map_err(|e| Error::CertificateError(e.to_string()))?
+    };
+
+    if valid {
+        Ok(())
+    } else {
+        Err(Error::SignatureVerificationFailed)
+    }
+}
+
+fn digest_for_alg(alg: i64) -> Result<MessageDigest, Error> {
+    match alg {
+        WEBAUTHN_COSE_ALGORITHM_IDENTIFIER_ES256 => Ok(MessageDigest::sha256()),
+        WEBAUTHN_COSE_ALGORITHM_IDENTIFIER_ES384 => Ok(MessageDigest::sha384()),
+        WEBAUTHN_COSE_ALGORITHM_IDENTIFIER_ES512 => Ok(MessageDigest::sha512()),
+        WEBAUTHN_COSE_ALGORITHM_IDENTIFIER_RS256 => Ok(MessageDigest::sha256()),
+        other => Err(Error::Other(format!("unsupported COSE algorithm: {}", other))),
+    }
+}
+
+fn ec2_group_for_curve(curve: i64) -> Result<EcGroup, Error> {
+    let nid = match curve {
+        WEBAUTHN_COSE_CURVE_P256 => Nid::X9_62_PRIME256V1,
+        WEBAUTHN_COSE_CURVE_P384 => Nid::SECP384R1,
+        WEBAUTHN_COSE_CURVE_P521 => Nid::SECP521R1,
+        other => return Err(Error::Other(format!("unsupported EC2 curve: {}", other))),
+    };
+    EcGroup::from_curve_name(nid).map_err(|e| Error::CertificateError(e.to_string()))
+}
+
+/// Builds a usable public key from a credential public key, for self attestation where the
+/// signature is verified with the credential's own key rather than a certificate's.
+fn credential_public_key_to_pkey(key: &CredentialPublicKey) -> Result<PKey<Public>, Error> {
+    match &key.key_material {
+        KeyMaterial::Ec2 { curve, coords } => {
+            let (x, y) = match coords {
+                Coordinates::Uncompressed { x, y } => (x.clone(), y.clone()),
+                Coordinates::Compressed { .. } => {
+                    return Err(Error::Other("compressed EC2 points are not yet supported".to_string()))
+                }
+            };
+
+            let group = ec2_group_for_curve(*curve)?;
+            let bn_x = BigNum::from_slice(&x).map_err(|e| Error::CertificateError(e.to_string()))?;
+            let bn_y = BigNum::from_slice(&y).map_err(|e| Error::CertificateError(e.to_string()))?;
+            let ec_key = EcKey::from_public_key_affine_coordinates(&group, &bn_x, &bn_y)
+                .map_err(|e| Error::CertificateError(e.to_string()))?;
+            PKey::from_ec_key(ec_key).map_err(|e| Error::CertificateError(e.to_string()))
+        }
+        KeyMaterial::Okp { x, .. } => {
+            PKey::public_key_from_raw_bytes(x, Id::ED25519).map_err(|e| Error::CertificateError(e.to_string()))
+        }
+        KeyMaterial::Rsa { n, e } => {
+            let modulus = BigNum::from_slice(n).map_err(|err| Error::CertificateError(err.to_string()))?;
+            let exponent = BigNum::from_slice(e).map_err(|err| Error::CertificateError(err.to_string()))?;
+            let rsa = Rsa::from_public_components(modulus, exponent).map_err(|err| Error::CertificateError(err.to_string()))?;
+            PKey::from_rsa(rsa).map_err(|err| Error::CertificateError(err.to_string()))
+        }
+    }
+}
+
+fn basic_constraints_ca_false(cert: &X509) -> Result<bool, Error> {
+    let der = cert.to_der().map_err(|e| Error::CertificateError(e.to_string()))?;
+    let (_, parsed) = x509_parser::parse_x509_certificate(&der).map_err(|e| Error::CertificateError(e.to_string()))?;
+
+    match parsed.basic_constraints() {
+        Ok(Some(bc)) => Ok(!bc.value.ca),
+        Ok(None) => Ok(true),
+        Err(e) => Err(Error::CertificateError(e.to_string())),
+    }
+}
+
+/// Reads the FIDO AAGUID extension from an attestation certificate, if present, stripping
+/// the ASN.1 OCTET STRING wrapper to recover the raw 16-byte AAGUID.
+fn fido_aaguid_extension(cert: &X509) -> Result<Option<[u8; 16]>, Error> {
+    let der = cert.to_der().map_err(|e| Error::CertificateError(e.to_string()))?;
+    let (_, parsed) = x509_parser::parse_x509_certificate(&der).map_err(|e| Error::CertificateError(e.to_string()))?;
+
+    // OID of the FIDO AAGUID certificate extension (id-fido-gen-ce-aaguid).
+    let extension = match parsed.get_extension_unique(&oid!(1.3.6.1.4.1.45724.1.1.4)) {
+        Ok(Some(ext)) => ext,
+        Ok(None) => return Ok(None),
+        Err(e) => return Err(Error::CertificateError(e.to_string())),
+    };
+
+    unwrap_aaguid_octet_string(extension.value).map(Some)
+}
+
+/// Strips the ASN.1 OCTET STRING wrapper (tag 0x04, length 0x10) from a FIDO AAGUID
+/// extension value, if present, to recover the raw 16-byte AAGUID.
+fn unwrap_aaguid_octet_string(value: &[u8]) -> Result<[u8; 16], Error> {
+    let raw = if value.len() == 18 && value[0] == 0x04 && value[1] == 0x10 {
+        &value[2..18]
+    } else if value.len() == 16 {
+        value
+    } else {
+        return Err(Error::CertificateError(
+            "FIDO AAGUID extension has an unexpected length".to_string(),
+        ));
+    };
+
+    let mut aaguid = [0u8; 16];
+    aaguid.copy_from_slice(raw);
+    Ok(aaguid)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::webauthn::proto::constants::WEBAUTHN_COSE_KEY_TYPE_EC2;
+    use crate::webauthn::proto::raw_message::{AttestationStatement, AuthenticatorData, AuthenticatorDataFlags};
+    use openssl::sign::Signer;
+
+    #[test]
+    fn unwraps_octet_string_wrapped_aaguid() {
+        let mut value = vec![0x04, 0x10];
+        value.extend_from_slice(&[0xAB; 16]);
+        assert_eq!(unwrap_aaguid_octet_string(&value).unwrap(), [0xAB; 16]);
+    }
+
+    #[test]
+    fn accepts_bare_sixteen_byte_aaguid() {
+        let value = [0xCD; 16];
+        assert_eq!(unwrap_aaguid_octet_string(&value).unwrap(), [0xCD; 16]);
+    }
+
+    #[test]
+    fn rejects_unexpected_length() {
+        let value = [0u8; 20];
+        assert!(unwrap_aaguid_octet_string(&value).is_err());
+    }
+
+    fn self_attested_object(client_data_hash: &[u8], aaguid: [u8; 16]) -> AttestationObject {
+        let group = EcGroup::from_curve_name(Nid::X9_62_PRIME256V1).unwrap();
+        let ec_key = EcKey::generate(&group).unwrap();
+        let mut ctx = openssl::bn::BigNumContext::new().unwrap();
+        let mut x = BigNum::new().unwrap();
+        let mut y = BigNum::new().unwrap();
+        ec_key
+            .public_key()
+            .affine_coordinates_gfp(&group, &mut x, &mut y, &mut ctx)
+            .unwrap();
+
+        let credential_public_key = CredentialPublicKey {
+            key_type: WEBAUTHN_COSE_KEY_TYPE_EC2,
+            alg: WEBAUTHN_COSE_ALGORITHM_IDENTIFIER_ES256,
+            key_material: KeyMaterial::Ec2 {
+                curve: WEBAUTHN_COSE_CURVE_P256,
+                coords: Coordinates::Uncompressed {
+                    x: x.to_vec(),
+                    y: y.to_vec(),
+                },
+            },
+        };
+
+        let auth_data = AuthenticatorData {
+            rp_id_hash: [0u8; 32],
+            flags: AuthenticatorDataFlags::ATTESTED_CREDENTIAL_DATA,
+            sign_count: 0,
+            attested_credential_data: Some(AttestedCredentialData {
+                aaguid,
+                credential_id: vec![0xAA; 16],
+                credential_public_key,
+            }),
+            extensions: serde_cbor::Value::Null,
+        };
+
+        let raw_auth_data = vec![0x11; 37];
+        let mut verification_data = raw_auth_data.clone();
+        verification_data.extend_from_slice(client_data_hash);
+
+        let pkey = PKey::from_ec_key(ec_key).unwrap();
+        let mut signer = Signer::new(MessageDigest::sha256(), &pkey).unwrap();
+        signer.update(&verification_data).unwrap();
+        let sig = signer.sign_to_vec().unwrap();
+
+        AttestationObject {
+            auth_data,
+            raw_auth_data,
+            fmt: "packed".to_string(),
+            att_stmt: AttestationStatement {
+                alg: WEBAUTHN_COSE_ALGORITHM_IDENTIFIER_ES256,
+                sig,
+                x5c: None,
+                ecdaa_key_id: None,
+            },
+        }
+    }
+
+    #[test]
+    fn verify_with_metadata_allows_self_attestation_with_no_certs_to_validate() {
+        let client_data_hash = [0x22; 32];
+        let aaguid = [0x33; 16];
+        let object = self_attested_object(&client_data_hash, aaguid);
+
+        let aaguid_str = aaguid.iter().map(|b| format!("{:02x}", b)).collect::<String>();
+        let payload = serde_json::json!({
+            "entries": [{
+                "aaguid": aaguid_str,
+                "metadataStatement": {
+                    "description": "Test Authenticator",
+                    "attestationRootCertificates": [],
+                    "authenticationAlgorithms": [],
+                    "keyProtection": [],
+                },
+            }],
+        });
+        let payload = base64::encode_config(serde_json::to_vec(&payload).unwrap(), base64::URL_SAFE_NO_PAD);
+        let jwt = format!("header.{}.sig", payload);
+        let metadata = MetadataService::from_mds_blob(&jwt).unwrap();
+
+        let (trust_path, description) = object.verify_with_metadata(&client_data_hash, &metadata).unwrap();
+        assert!(trust_path.certificates.is_empty());
+        assert_eq!(description, None);
+    }
+}
@@ -0,0 +1,42 @@
+use std::fmt;
+
+#[derive(Debug)]
+pub enum Error {
+    Io(std::io::Error),
+    CborError(serde_cbor::Error),
+    Base64Error(base64::DecodeError),
+    CertificateError(String),
+    SignatureVerificationFailed,
+    UnsupportedAttestationFormat(String),
+    PossibleCloneDetected,
+    Other(String),
+}
+
+impl fmt::Display for Error {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        match self {
+            Error::Io(e) => write!(f, "io error: {}", e),
+            Error::CborError(e) => write!(f, "cbor error: {}", e),
+            Error::Base64Error(e) => write!(f, "base64 error: {}", e),
+            Error::CertificateError(s) => write!(f, "certificate error: {}", s),
+            Error::SignatureVerificationFailed => write!(f, "attestation signature verification failed"),
+            Error::UnsupportedAttestationFormat(fmt_name) => write!(f, "unsupported attestation format: {}", fmt_name),
+            Error::PossibleCloneDetected => write!(f, "signature counter did not advance; possible cloned authenticator"),
+            Error::Other(s) => write!(f, "{}", s),
+        }
+    }
+}
+
+impl std::error::Error for Error {}
+
+impl From<std::io::Error> for Error {
+    fn from(e: std::io::Error) -> Self {
+        Error::Io(e)
+    }
+}
+
+impl From<base64::DecodeError> for Error {
+    fn from(e: base64::DecodeError) -> Self {
+        Error::Base64Error(e)
+    }
+}
@@ -0,0 +1,140 @@
+//! FIDO Metadata Service (MDS) support: loading a signed MDS BLOB and looking up
+//! per-authenticator trust policy by AAGUID.
+
+use crate::webauthn::error::Error;
+use openssl::x509::X509;
+use serde_derive::Deserialize;
+use std::collections::HashMap;
+
+/// What the metadata service knows about a single authenticator model.
+#[derive(Debug, Clone)]
+pub struct MetadataEntry {
+    pub aaguid: [u8; 16],
+    pub description: String,
+    pub attestation_root_certificates: Vec<X509>,
+    pub authentication_algorithms: Vec<String>,
+    pub key_protection: Vec<String>,
+}
+
+/// An in-memory index of MDS entries keyed by AAGUID, loaded once from a BLOB and then
+/// consulted per attestation.
+#[derive(Debug, Clone, Default)]
+pub struct MetadataService {
+    entries: HashMap<[u8; 16], MetadataEntry>,
+}
+
+impl MetadataService {
+    /// Parses a FIDO MDS BLOB: a JWT whose payload is the metadata entry list. The JWT
+    /// signature itself is not verified here; callers fetching the BLOB over TLS from the
+    /// official MDS endpoint may choose to additionally check it against the MDS root.
+    pub fn from_mds_blob(jwt: &str) -> Result<Self, Error> {
+        let payload = jwt
+            .split('.')
+            .nth(1)
+            .ok_or_else(|| Error::Other("MDS BLOB is not a JWT".to_string()))?;
+        let payload = base64::decode_config(payload, base64::URL_SAFE_NO_PAD)?;
+
+        let blob: MdsBlob = serde_json::from_slice(&payload).map_err(|e| Error::Other(e.to_string()))?;
+
+        let mut entries = HashMap::new();
+        for raw_entry in blob.entries {
+            let aaguid = match raw_entry.aaguid {
+                Some(aaguid) => parse_aaguid(&aaguid)?,
+                None => continue,
+            };
+
+            let attestation_root_certificates = raw_entry
+                .metadata_statement
+                .attestation_root_certificates
+                .iter()
+                .map(|der_b64| {
+                    let der = base64::decode(der_b64)?;
+                    X509::from_der(&der).map_err(|e| Error::CertificateError(e.to_string()))
+                })
+                .collect::<Result<Vec<_>, Error>>()?;
+
+            entries.insert(
+                aaguid,
+                MetadataEntry {
+                    aaguid,
+                    description: raw_entry.metadata_statement.description,
+                    attestation_root_certificates,
+                    authentication_algorithms: raw_entry.metadata_statement.authentication_algorithms,
+                    key_protection: raw_entry.metadata_statement.key_protection,
+                },
+            );
+        }
+
+        Ok(MetadataService { entries })
+    }
+
+    pub fn metadata_for(&self, aaguid: &[u8; 16]) -> Option<&MetadataEntry> {
+        self.entries.get(aaguid)
+    }
+}
+
+fn parse_aaguid(value: &str) -> Result<[u8; 16], Error> {
+    let hex: String = value.chars().filter(|c| *c != '-').collect();
+    if hex.len() != 32 {
+        return Err(Error::Other(format!("malformed AAGUID: {}", value)));
+    }
+
+    let mut aaguid = [0u8; 16];
+    for (i, byte) in aaguid.iter_mut().enumerate() {
+        *byte = u8::from_str_radix(&hex[i * 2..i * 2 + 2], 16)
+            .map_err(|_| Error::Other(format!("malformed AAGUID: {}", value)))?;
+    }
+    Ok(aaguid)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn parses_hyphenated_aaguid() {
+        assert_eq!(
+            parse_aaguid("4e4e4e4e-4e4e-4e4e-4e4e-4e4e4e4e4e4e").unwrap(),
+            [0x4e; 16]
+        );
+    }
+
+    #[test]
+    fn parses_aaguid_without_hyphens() {
+        assert_eq!(parse_aaguid(&"ab".repeat(16)).unwrap(), [0xab; 16]);
+    }
+
+    #[test]
+    fn rejects_wrong_length_aaguid() {
+        assert!(parse_aaguid("4e4e4e4e-4e4e-4e4e-4e4e-4e4e4e4e").is_err());
+    }
+
+    #[test]
+    fn rejects_non_hex_aaguid() {
+        assert!(parse_aaguid(&"zz".repeat(16)).is_err());
+    }
+}
+
+#[derive(Deserialize)]
+struct MdsBlob {
+    entries: Vec<MdsEntry>,
+}
+
+#[derive(Deserialize)]
+#[serde(rename_all = "camelCase")]
+struct MdsEntry {
+    aaguid: Option<String>,
+    metadata_statement: MdsMetadataStatement,
+}
+
+#[derive(Deserialize)]
+#[serde(rename_all = "camelCase")]
+struct MdsMetadataStatement {
+    description: String,
+    #[serde(default)]
+    attestation_root_certificates: Vec<String>,
+    #[serde(default)]
+    authentication_algorithms: Vec<String>,
+    #[serde(default)]
+    key_protection: Vec<String>,
+}
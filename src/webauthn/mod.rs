@@ -0,0 +1,5 @@
+pub mod error;
+pub mod proto;
+pub mod attestation;
+pub mod metadata;
+pub mod sign_count;
@@ -2,4 +2,19 @@ pub const WEBAUTHN_CHALLENGE_LENGTH: usize = 32;
 pub const WEBAUTHN_CREDENTIAL_ID_LENGTH: usize = 16;
 
 pub const WEBAUTHN_COSE_ALGORITHM_IDENTIFIER_ES256: i64 = -7;
-pub const WEBAUTHN_COSE_ALGORITHM_IDENTIFIER_RS256: i64 = -257;
\ No newline at end of file
+pub const WEBAUTHN_COSE_ALGORITHM_IDENTIFIER_EDDSA: i64 = -8;
+pub const WEBAUTHN_COSE_ALGORITHM_IDENTIFIER_ES384: i64 = -35;
+pub const WEBAUTHN_COSE_ALGORITHM_IDENTIFIER_ES512: i64 = -36;
+pub const WEBAUTHN_COSE_ALGORITHM_IDENTIFIER_RS256: i64 = -257;
+
+pub const WEBAUTHN_COSE_KEY_TYPE_OKP: i64 = 1;
+pub const WEBAUTHN_COSE_KEY_TYPE_EC2: i64 = 2;
+pub const WEBAUTHN_COSE_KEY_TYPE_RSA: i64 = 3;
+
+pub const WEBAUTHN_COSE_CURVE_P256: i64 = 1;
+pub const WEBAUTHN_COSE_CURVE_P384: i64 = 2;
+pub const WEBAUTHN_COSE_CURVE_P521: i64 = 3;
+pub const WEBAUTHN_COSE_CURVE_ED25519: i64 = 6;
+
+pub const ECDSA_Y_PREFIX_POSITIVTE: u8 = 0x02;
+pub const ECDSA_Y_PREFIX_NEGATIVE: u8 = 0x03;
\ No newline at end of file
@@ -1,11 +1,16 @@
 use crate::webauthn::error::Error;
 use serde_derive::*;
+use serde::{Deserialize, Deserializer, Serialize, Serializer};
 use serde_cbor::Value;
 use std::io::{Cursor, Read};
 use byteorder::{ReadBytesExt, BigEndian};
 use bytes::Buf;
 use std::collections::BTreeMap;
-use crate::webauthn::proto::constants::{ECDSA_Y_PREFIX_POSITIVTE, ECDSA_Y_PREFIX_NEGATIVE};
+use crate::webauthn::proto::constants::{
+    ECDSA_Y_PREFIX_POSITIVTE, ECDSA_Y_PREFIX_NEGATIVE,
+    WEBAUTHN_COSE_KEY_TYPE_EC2, WEBAUTHN_COSE_KEY_TYPE_OKP, WEBAUTHN_COSE_KEY_TYPE_RSA,
+    WEBAUTHN_COSE_CURVE_P256, WEBAUTHN_COSE_CURVE_P384, WEBAUTHN_COSE_CURVE_P521,
+};
 
 #[derive(Serialize, Deserialize, Clone, Debug)]
 #[serde(rename_all = "camelCase")]
@@ -36,28 +41,74 @@ pub struct AttestationStatement {
     pub ecdaa_key_id: Option<serde_cbor::Value>,
 }
 
+bitflags::bitflags! {
+    pub struct AuthenticatorDataFlags: u8 {
+        const USER_PRESENT = 0b0000_0001;
+        const USER_VERIFIED = 0b0000_0100;
+        const BACKUP_ELIGIBLE = 0b0000_1000;
+        const BACKUP_STATE = 0b0001_0000;
+        const ATTESTED_CREDENTIAL_DATA = 0b0100_0000;
+        const EXTENSION_DATA = 0b1000_0000;
+    }
+}
+
+impl Serialize for AuthenticatorDataFlags {
+    fn serialize<S>(&self, serializer: S) -> Result<S::Ok, S::Error>
+    where
+        S: Serializer,
+    {
+        serializer.serialize_u8(self.bits())
+    }
+}
+
+impl<'de> Deserialize<'de> for AuthenticatorDataFlags {
+    fn deserialize<D>(deserializer: D) -> Result<Self, D::Error>
+    where
+        D: Deserializer<'de>,
+    {
+        let bits = u8::deserialize(deserializer)?;
+        Ok(AuthenticatorDataFlags::from_bits_truncate(bits))
+    }
+}
+
 #[derive(Serialize, Deserialize, Clone, Debug)]
 #[serde(rename_all = "camelCase")]
 pub struct AuthenticatorData {
     pub rp_id_hash: [u8; 32],
-    pub flags: u8,
+    pub flags: AuthenticatorDataFlags,
     pub sign_count: u32,
     pub attested_credential_data: Option<AttestedCredentialData>,
     pub extensions: serde_cbor::Value
 }
 
 impl AuthenticatorData {
+    pub fn user_present(&self) -> bool {
+        self.flags.contains(AuthenticatorDataFlags::USER_PRESENT)
+    }
+
+    pub fn user_verified(&self) -> bool {
+        self.flags.contains(AuthenticatorDataFlags::USER_VERIFIED)
+    }
+
+    pub fn backup_eligible(&self) -> bool {
+        self.flags.contains(AuthenticatorDataFlags::BACKUP_ELIGIBLE)
+    }
+
+    pub fn backup_state(&self) -> bool {
+        self.flags.contains(AuthenticatorDataFlags::BACKUP_STATE)
+    }
+
     pub fn from_vec(data: Vec<u8>) -> Result<(Self, Vec<u8>), Error> {
         let mut cursor = Cursor::new(data);
 
         let mut rp_id_hash = [0u8; 32];
         cursor.read_exact(&mut rp_id_hash)?;
 
-        let flags = cursor.read_u8()?;
+        let flags = AuthenticatorDataFlags::from_bits_truncate(cursor.read_u8()?);
 
         let sign_count = cursor.read_u32::<BigEndian>()?;
 
-        let attested_credential_data = if cursor.remaining() > 16 {
+        let attested_credential_data = if flags.contains(AuthenticatorDataFlags::ATTESTED_CREDENTIAL_DATA) {
             let mut aaguid = [0u8; 16];
             cursor.read_exact(&mut aaguid)?;
 
@@ -66,12 +117,18 @@ impl AuthenticatorData {
             let mut credential_id = vec![0u8; length as usize];
             cursor.read_exact(&mut credential_id[..])?;
 
+            // The credential public key CBOR map may be followed by an extensions map, so we
+            // decode it from a scratch reader and track how many bytes it actually consumed
+            // rather than assuming it's the last thing in the buffer.
             let mut remaining = vec![0u8; cursor.remaining()];
             cursor.read_exact(&mut remaining[..])?;
+            let mut key_reader = Cursor::new(remaining.as_slice());
+            let credential_public_key_value: serde_cbor::Value =
+                serde_cbor::from_reader(&mut key_reader).map_err(Error::CborError)?;
+            let consumed = key_reader.position();
+            cursor.set_position(cursor.position() - remaining.len() as u64 + consumed);
 
-            let remaining_value = serde_cbor::from_slice::<serde_cbor::Value>(remaining.as_slice()).map_err(|e| Error::CborError(e))?;
-
-            let credential_public_key = CredentialPublicKey::from_value(remaining_value)?;
+            let credential_public_key = CredentialPublicKey::from_value(credential_public_key_value)?;
 
             Some(AttestedCredentialData {
                 aaguid,
@@ -80,12 +137,20 @@ impl AuthenticatorData {
             })
         } else { None };
 
+        let extensions = if flags.contains(AuthenticatorDataFlags::EXTENSION_DATA) {
+            let mut remaining = vec![0u8; cursor.remaining()];
+            cursor.read_exact(&mut remaining[..])?;
+            serde_cbor::from_slice::<serde_cbor::Value>(&remaining).map_err(Error::CborError)?
+        } else {
+            Value::Null
+        };
+
         Ok((AuthenticatorData {
             rp_id_hash,
             flags,
             sign_count,
             attested_credential_data,
-            extensions: Value::Null
+            extensions,
         }, cursor.into_inner()))
     }
 }
@@ -102,90 +167,114 @@ pub struct AttestedCredentialData {
 pub struct CredentialPublicKey {
     pub key_type: i64,
     pub alg: i64,
-    pub curve: i64,
-    pub coords: Coordinates,
+    pub key_material: KeyMaterial,
+}
+
+/// Key material for a COSE key, shaped by `kty` (label 1). Only EC2, OKP and RSA are
+/// registered with WebAuthn today.
+#[derive(Serialize, Deserialize, Clone, Debug)]
+pub enum KeyMaterial {
+    Ec2 { curve: i64, coords: Coordinates },
+    Okp { curve: i64, x: Vec<u8> },
+    Rsa { n: Vec<u8>, e: Vec<u8> },
 }
 
 impl CredentialPublicKey {
     pub fn from_value(value: serde_cbor::Value) -> Result<Self, Error> {
         let map = match value {
             Value::Map(m) => m,
-            _ => { BTreeMap::new() },
+            _ => BTreeMap::new(),
         };
 
-        let key_type = map.get(&Value::Integer(1)).map(|val| {
-            match val {
-                Value::Integer(i) => *i as i64,
-                _ => 0i64,
-            }
-        }).ok_or(Error::Other("Key type missing".to_string()))?;
-
-        let alg = map.get(&Value::Integer(3)).map(|val| {
-            match val {
-                Value::Integer(i) => *i as i64,
-                _ => 0i64,
+        let key_type = get_cose_int(&map, 1, "key type")?;
+        let alg = get_cose_int(&map, 3, "algorithm")?;
+
+        let key_material = match key_type {
+            WEBAUTHN_COSE_KEY_TYPE_EC2 => {
+                let curve = get_cose_int(&map, -1, "curve")?;
+                let coord_len = ec2_coordinate_length(curve)?;
+                let x = get_cose_bytes(&map, -2, "x coordinate", Some(coord_len))?;
+
+                let coords = match map.get(&Value::Integer(-3)) {
+                    Some(Value::Bytes(y)) if y.len() == coord_len => {
+                        Coordinates::Uncompressed { x, y: y.clone() }
+                    }
+                    Some(Value::Bool(negative)) => Coordinates::Compressed {
+                        x,
+                        y_sign: if *negative { ECDSA_Y_PREFIX_NEGATIVE } else { ECDSA_Y_PREFIX_POSITIVTE },
+                    },
+                    _ => return Err(Error::Other("y coordinate missing or malformed".to_string())),
+                };
+
+                KeyMaterial::Ec2 { curve, coords }
             }
-        }).ok_or(Error::Other("algorithm missing".to_string()))?;
-
-        let curve = map.get(&Value::Integer(-1)).map(|val| {
-            match val {
-                Value::Integer(i) => *i as i64,
-                _ => 0i64,
+            WEBAUTHN_COSE_KEY_TYPE_OKP => {
+                let curve = get_cose_int(&map, -1, "curve")?;
+                let x = get_cose_bytes(&map, -2, "x coordinate", Some(32))?;
+                KeyMaterial::Okp { curve, x }
             }
-        }).ok_or(Error::Other("curve missing".to_string()))?;
-
-        let x = map.get(&Value::Integer(-2)).and_then(|val| {
-            match val {
-                Value::Bytes(i) => {
-                    let mut array = [0u8; 32];
-                    array.copy_from_slice(&i[0..32]);
-                    Some(array)
-                },
-                _ => None,
+            WEBAUTHN_COSE_KEY_TYPE_RSA => {
+                let n = get_cose_bytes(&map, -1, "modulus", None)?;
+                let e = get_cose_bytes(&map, -2, "exponent", None)?;
+                KeyMaterial::Rsa { n, e }
             }
-
-        }).ok_or(Error::Other("x coordinate missing".to_string()))?;
-
-        let coords = map.get(&Value::Integer(-3)).and_then(|val| {
-            match val {
-                Value::Bytes(i) => {
-                    let mut array = [0u8; 32];
-                    array.copy_from_slice(&i[0..32]);
-                    Some(Coordinates::Uncompressed { x, y: array, })
-                },
-
-                Value::Bool(b) => {
-                    Some(Coordinates::Compressed { x, y: if *b { ECDSA_Y_PREFIX_NEGATIVE } else { ECDSA_Y_PREFIX_POSITIVTE } })
-                }
-                _ => None,
-            }
-
-        }).ok_or(Error::Other("y coordinate missing".to_string()))?;
+            other => return Err(Error::Other(format!("unsupported COSE key type: {}", other))),
+        };
 
         Ok(CredentialPublicKey {
             key_type,
             alg,
-            curve,
-            coords,
+            key_material,
         })
     }
 }
 
+/// Coordinate size, in bytes, for the COSE-registered NIST curves used by EC2 keys.
+fn ec2_coordinate_length(curve: i64) -> Result<usize, Error> {
+    match curve {
+        WEBAUTHN_COSE_CURVE_P256 => Ok(32),
+        WEBAUTHN_COSE_CURVE_P384 => Ok(48),
+        WEBAUTHN_COSE_CURVE_P521 => Ok(66),
+        other => Err(Error::Other(format!("unsupported EC2 curve: {}", other))),
+    }
+}
+
+fn get_cose_int(map: &BTreeMap<Value, Value>, label: i64, name: &str) -> Result<i64, Error> {
+    match map.get(&Value::Integer(label as i128)) {
+        Some(Value::Integer(i)) => Ok(*i as i64),
+        _ => Err(Error::Other(format!("{} missing", name))),
+    }
+}
+
+fn get_cose_bytes(map: &BTreeMap<Value, Value>, label: i64, name: &str, expected_len: Option<usize>) -> Result<Vec<u8>, Error> {
+    match map.get(&Value::Integer(label as i128)) {
+        Some(Value::Bytes(bytes)) => {
+            if let Some(len) = expected_len {
+                if bytes.len() != len {
+                    return Err(Error::Other(format!("{} has unexpected length {}", name, bytes.len())));
+                }
+            }
+            Ok(bytes.clone())
+        }
+        _ => Err(Error::Other(format!("{} missing", name))),
+    }
+}
+
 
 
 pub trait Message {
-    fn from_base64(string: &String) -> Result<Self, Error> where Self: Sized;
+    fn from_base64(string: &str) -> Result<Self, Error> where Self: Sized;
     fn from_bytes(raw_values: &[u8]) -> Result<Self, Error> where Self: Sized;
 }
 
 impl Message for AttestationObject {
-    fn from_base64(string: &String) -> Result<Self, Error> where Self: Sized {
+    fn from_base64(string: &str) -> Result<Self, Error> where Self: Sized {
         let raw_values = base64::decode(string)?;
         Self::from_bytes(raw_values.as_slice())
     }
 
     fn from_bytes(raw_values: &[u8]) -> Result<Self, Error> where Self: Sized {
-        let value = serde_cbor::from_slice::<RawAttestationObject>(raw_values).map_err(|e| Error::CborError(e))?;
+        let value = serde_cbor::from_slice::<RawAttestationObject>(raw_values).map_err(Error::CborError)?;
 
         let data = match value.auth_data {
             Value::Bytes(vec) => Ok(vec),
@@ -205,6 +294,130 @@ impl Message for AttestationObject {
 
 #[derive(Serialize, Deserialize, Clone, Debug)]
 pub enum Coordinates {
-    Compressed { x: [u8; 32], y: u8 },
-    Uncompressed { x: [u8; 32], y: [u8; 32] },
+    Compressed { x: Vec<u8>, y_sign: u8 },
+    Uncompressed { x: Vec<u8>, y: Vec<u8> },
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::webauthn::proto::constants::WEBAUTHN_COSE_CURVE_ED25519;
+
+    fn cose_map(entries: Vec<(i128, Value)>) -> Value {
+        Value::Map(entries.into_iter().map(|(k, v)| (Value::Integer(k), v)).collect())
+    }
+
+    #[test]
+    fn parses_ec2_p256_key() {
+        let value = cose_map(vec![
+            (1, Value::Integer(WEBAUTHN_COSE_KEY_TYPE_EC2 as i128)),
+            (3, Value::Integer(-7)),
+            (-1, Value::Integer(WEBAUTHN_COSE_CURVE_P256 as i128)),
+            (-2, Value::Bytes(vec![1u8; 32])),
+            (-3, Value::Bytes(vec![2u8; 32])),
+        ]);
+
+        let key = CredentialPublicKey::from_value(value).unwrap();
+        match key.key_material {
+            KeyMaterial::Ec2 { curve, coords: Coordinates::Uncompressed { x, y } } => {
+                assert_eq!(curve, WEBAUTHN_COSE_CURVE_P256);
+                assert_eq!(x, vec![1u8; 32]);
+                assert_eq!(y, vec![2u8; 32]);
+            }
+            other => panic!("expected EC2 uncompressed key, got {:?}", other),
+        }
+    }
+
+    #[test]
+    fn parses_ec2_p384_key_with_curve_sized_coordinates() {
+        let value = cose_map(vec![
+            (1, Value::Integer(WEBAUTHN_COSE_KEY_TYPE_EC2 as i128)),
+            (3, Value::Integer(-35)),
+            (-1, Value::Integer(WEBAUTHN_COSE_CURVE_P384 as i128)),
+            (-2, Value::Bytes(vec![3u8; 48])),
+            (-3, Value::Bytes(vec![4u8; 48])),
+        ]);
+
+        let key = CredentialPublicKey::from_value(value).unwrap();
+        match key.key_material {
+            KeyMaterial::Ec2 { coords: Coordinates::Uncompressed { x, y }, .. } => {
+                assert_eq!(x.len(), 48);
+                assert_eq!(y.len(), 48);
+            }
+            other => panic!("expected EC2 uncompressed key, got {:?}", other),
+        }
+    }
+
+    #[test]
+    fn parses_okp_ed25519_key() {
+        let value = cose_map(vec![
+            (1, Value::Integer(WEBAUTHN_COSE_KEY_TYPE_OKP as i128)),
+            (3, Value::Integer(-8)),
+            (-1, Value::Integer(WEBAUTHN_COSE_CURVE_ED25519 as i128)),
+            (-2, Value::Bytes(vec![5u8; 32])),
+        ]);
+
+        let key = CredentialPublicKey::from_value(value).unwrap();
+        match key.key_material {
+            KeyMaterial::Okp { curve, x } => {
+                assert_eq!(curve, WEBAUTHN_COSE_CURVE_ED25519);
+                assert_eq!(x, vec![5u8; 32]);
+            }
+            other => panic!("expected OKP key, got {:?}", other),
+        }
+    }
+
+    #[test]
+    fn parses_rsa_key() {
+        let value = cose_map(vec![
+            (1, Value::Integer(WEBAUTHN_COSE_KEY_TYPE_RSA as i128)),
+            (3, Value::Integer(-257)),
+            (-1, Value::Bytes(vec![6u8; 256])),
+            (-2, Value::Bytes(vec![0x01, 0x00, 0x01])),
+        ]);
+
+        let key = CredentialPublicKey::from_value(value).unwrap();
+        match key.key_material {
+            KeyMaterial::Rsa { n, e } => {
+                assert_eq!(n.len(), 256);
+                assert_eq!(e, vec![0x01, 0x00, 0x01]);
+            }
+            other => panic!("expected RSA key, got {:?}", other),
+        }
+    }
+
+    #[test]
+    fn rejects_unsupported_key_type() {
+        let value = cose_map(vec![
+            (1, Value::Integer(99)),
+            (3, Value::Integer(-7)),
+        ]);
+
+        assert!(CredentialPublicKey::from_value(value).is_err());
+    }
+
+    #[test]
+    fn rejects_ec2_coordinate_with_wrong_length_for_curve() {
+        let value = cose_map(vec![
+            (1, Value::Integer(WEBAUTHN_COSE_KEY_TYPE_EC2 as i128)),
+            (3, Value::Integer(-7)),
+            (-1, Value::Integer(WEBAUTHN_COSE_CURVE_P256 as i128)),
+            (-2, Value::Bytes(vec![1u8; 48])),
+            (-3, Value::Bytes(vec![2u8; 32])),
+        ]);
+
+        assert!(CredentialPublicKey::from_value(value).is_err());
+    }
+
+    #[test]
+    fn rejects_unsupported_ec2_curve() {
+        assert!(ec2_coordinate_length(99).is_err());
+    }
+
+    #[test]
+    fn ec2_coordinate_length_matches_each_curve() {
+        assert_eq!(ec2_coordinate_length(WEBAUTHN_COSE_CURVE_P256).unwrap(), 32);
+        assert_eq!(ec2_coordinate_length(WEBAUTHN_COSE_CURVE_P384).unwrap(), 48);
+        assert_eq!(ec2_coordinate_length(WEBAUTHN_COSE_CURVE_P521).unwrap(), 66);
+    }
 }
\ No newline at end of file
@@ -0,0 +1,97 @@
+//! Clone detection based on the authenticator's signature counter.
+
+use crate::webauthn::error::Error;
+use std::collections::BTreeMap;
+use std::sync::Mutex;
+
+/// Tracks the last-seen signature counter per credential so it can be used to detect cloned
+/// authenticators across assertions, per the WebAuthn signature counter rules.
+pub trait SignCountVerifier {
+    /// Checks `sign_count` against the stored value for `credential_id` and, if it's valid,
+    /// updates the stored value. Returns `Error::PossibleCloneDetected` if the counter went
+    /// backwards or failed to advance while nonzero.
+    fn check_and_update(&self, credential_id: &[u8], sign_count: u32) -> Result<(), Error>;
+}
+
+/// In-memory default `SignCountVerifier`, keyed by credential id. State is not persisted
+/// across process restarts; relying parties needing durability should back this with their
+/// own store instead.
+#[derive(Default)]
+pub struct InMemorySignCountVerifier {
+    counts: Mutex<BTreeMap<Vec<u8>, u32>>,
+}
+
+impl InMemorySignCountVerifier {
+    pub fn new() -> Self {
+        Self::default()
+    }
+}
+
+impl SignCountVerifier for InMemorySignCountVerifier {
+    fn check_and_update(&self, credential_id: &[u8], sign_count: u32) -> Result<(), Error> {
+        let mut counts = self.counts.lock().expect("sign count store lock poisoned");
+        let stored = counts.get(credential_id).copied().unwrap_or(0);
+
+        // Per the WebAuthn rules, a counter that is 0 on both stored and incoming means the
+        // authenticator doesn't support one; skip the check rather than flagging a clone.
+        if stored == 0 && sign_count == 0 {
+            return Ok(());
+        }
+
+        if sign_count <= stored {
+            return Err(Error::PossibleCloneDetected);
+        }
+
+        counts.insert(credential_id.to_vec(), sign_count);
+        Ok(())
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn accepts_and_stores_first_nonzero_counter() {
+        let verifier = InMemorySignCountVerifier::new();
+        assert!(verifier.check_and_update(b"cred", 1).is_ok());
+        assert!(matches!(
+            verifier.check_and_update(b"cred", 1),
+            Err(Error::PossibleCloneDetected)
+        ));
+    }
+
+    #[test]
+    fn accepts_strictly_increasing_counter() {
+        let verifier = InMemorySignCountVerifier::new();
+        verifier.check_and_update(b"cred", 5).unwrap();
+        assert!(verifier.check_and_update(b"cred", 6).is_ok());
+    }
+
+    #[test]
+    fn rejects_equal_nonzero_counter() {
+        let verifier = InMemorySignCountVerifier::new();
+        verifier.check_and_update(b"cred", 5).unwrap();
+        assert!(matches!(
+            verifier.check_and_update(b"cred", 5),
+            Err(Error::PossibleCloneDetected)
+        ));
+    }
+
+    #[test]
+    fn rejects_backwards_counter() {
+        let verifier = InMemorySignCountVerifier::new();
+        verifier.check_and_update(b"cred", 5).unwrap();
+        assert!(matches!(
+            verifier.check_and_update(b"cred", 4),
+            Err(Error::PossibleCloneDetected)
+        ));
+    }
+
+    #[test]
+    fn treats_counter_unsupported_as_no_op() {
+        let verifier = InMemorySignCountVerifier::new();
+        assert!(verifier.check_and_update(b"cred", 0).is_ok());
+        assert!(verifier.check_and_update(b"cred", 0).is_ok());
+    }
+}